@@ -0,0 +1,240 @@
+//! `mmap`/`munmap`: anonymous and file-backed memory mappings
+//!
+//! Anonymous mappings are mapped and frame-backed up front, same as any
+//! other framed area. File-backed mappings reserve their frames the same
+//! way (so [`MemorySet`]'s own `areas` bookkeeping -- the thing that makes
+//! `munmap`/process-exit reclaim frames correctly -- covers them too) but
+//! immediately drop the page table entry, leaving the already-allocated
+//! frame parked in [`FILE_BACKED_PAGES`]. That turns the first touch of
+//! each page into a real page fault, which [`handle_mmap_page_fault`]
+//! services by remapping the same frame and reading its block in from the
+//! file.
+
+use alloc::collections::BTreeMap;
+use alloc::sync::Arc;
+
+use bitflags::bitflags;
+use lazy_static::lazy_static;
+
+use crate::config::PAGE_SIZE;
+use crate::fs::File;
+use crate::mm::{MapPermission, PhysPageNum, PTEFlags, VirtAddr, VirtPageNum};
+use crate::sync::UPSafeCell;
+use crate::task::{current_process, current_task};
+
+bitflags! {
+    /// `port`/`prot` bits accepted by `sys_mmap`/`sys_mmap_file`.
+    pub struct MmapProt: usize {
+        const READ = 1 << 0;
+        const WRITE = 1 << 1;
+        const EXEC = 1 << 2;
+    }
+}
+
+fn mmap_permission(prot: MmapProt) -> MapPermission {
+    let mut perm = MapPermission::U;
+    if prot.contains(MmapProt::READ) {
+        perm |= MapPermission::R;
+    }
+    if prot.contains(MmapProt::WRITE) {
+        perm |= MapPermission::W;
+    }
+    if prot.contains(MmapProt::EXEC) {
+        perm |= MapPermission::X;
+    }
+    perm
+}
+
+/// A reserved-but-not-yet-faulted-in file-backed page: the frame behind it
+/// is already allocated (owned by its `MapArea`'s bookkeeping) but not
+/// mapped into the page table until [`handle_mmap_page_fault`] services it.
+struct FileBacking {
+    file: Arc<dyn File>,
+    /// offset into `file` of the block backing this page
+    offset: usize,
+    /// the frame reserved for this page when the mapping was created
+    ppn: PhysPageNum,
+    permission: MapPermission,
+    /// whether to flush this page's contents back to `file` on unmap:
+    /// i.e. whether it was mapped writable and shared
+    writable_shared: bool,
+    /// whether the page has been faulted in yet
+    faulted: bool,
+}
+
+lazy_static! {
+    /// File-backed mmap pages, keyed by (owning process pid, virtual page).
+    static ref FILE_BACKED_PAGES: UPSafeCell<BTreeMap<(usize, VirtPageNum), FileBacking>> =
+        unsafe { UPSafeCell::new(BTreeMap::new()) };
+}
+
+fn page_range(start: usize, len: usize) -> Option<(VirtAddr, VirtAddr, VirtPageNum, VirtPageNum)> {
+    if start % PAGE_SIZE != 0 || len == 0 {
+        return None;
+    }
+    let start_va = VirtAddr::from(start);
+    let end_va = VirtAddr::from(start + len);
+    Some((start_va, end_va, start_va.floor(), end_va.ceil()))
+}
+
+/// `sys_mmap(start, len, port)`: anonymous mapping.
+pub fn mmap(start: usize, len: usize, port: usize) -> isize {
+    let Some((start_va, end_va, start_vpn, end_vpn)) = page_range(start, len) else {
+        return -1;
+    };
+    let prot = match MmapProt::from_bits(port) {
+        Some(prot) if !prot.is_empty() => prot,
+        _ => return -1,
+    };
+
+    let process = current_process();
+    let mut inner = process.inner_exclusive_access();
+    if inner.memory_set.areas.iter().any(|area| {
+        let s = area.vpn_range.get_start();
+        let e = area.vpn_range.get_end();
+        start_vpn < e && s < end_vpn
+    }) {
+        return -1;
+    }
+    inner
+        .memory_set
+        .insert_framed_area(start_va, end_va, mmap_permission(prot));
+    0
+}
+
+/// `sys_mmap_file(start, len, prot, fd, offset)`: map an open file
+/// descriptor, populating frames lazily on first access.
+pub fn mmap_file(start: usize, len: usize, prot: usize, fd: usize, file_offset: usize) -> isize {
+    let Some((start_va, end_va, start_vpn, end_vpn)) = page_range(start, len) else {
+        return -1;
+    };
+    let prot = match MmapProt::from_bits(prot) {
+        Some(prot) if !prot.is_empty() => prot,
+        _ => return -1,
+    };
+
+    let task = current_task().unwrap();
+    let file = {
+        let task_inner = task.inner_exclusive_access();
+        match task_inner.fd_table.get(fd) {
+            Some(Some(file)) if file.readable() => Arc::clone(file),
+            _ => return -1,
+        }
+    };
+
+    let process = current_process();
+    let pid = process.getpid();
+    let mut inner = process.inner_exclusive_access();
+    if inner.memory_set.areas.iter().any(|area| {
+        let s = area.vpn_range.get_start();
+        let e = area.vpn_range.get_end();
+        start_vpn < e && s < end_vpn
+    }) {
+        return -1;
+    }
+
+    let permission = mmap_permission(prot);
+    inner
+        .memory_set
+        .insert_framed_area(start_va, end_va, permission);
+
+    let writable_shared = permission.contains(MapPermission::W) && file.writable();
+    let mut pages = FILE_BACKED_PAGES.exclusive_access();
+    let mut vpn = start_vpn;
+    let mut offset = file_offset;
+    while vpn < end_vpn {
+        let ppn = inner.memory_set.translate(vpn).unwrap().ppn();
+        // Drop the PTE so the first real access faults; the frame itself
+        // stays allocated (and owned) by the area's own bookkeeping.
+        inner.memory_set.page_table_mut().unmap(vpn);
+        pages.insert(
+            (pid, vpn),
+            FileBacking {
+                file: Arc::clone(&file),
+                offset,
+                ppn,
+                permission,
+                writable_shared,
+                faulted: false,
+            },
+        );
+        vpn = VirtPageNum::from(usize::from(vpn) + 1);
+        offset += PAGE_SIZE;
+    }
+    0
+}
+
+/// Service a page fault at `va`. Returns `true` if it was a first touch of
+/// a lazily-populated file-backed mmap page (now mapped and readable);
+/// `false` if this fault is none of mmap's business and the caller should
+/// treat it as a real fault.
+pub fn handle_mmap_page_fault(va: VirtAddr) -> bool {
+    let process = current_process();
+    let pid = process.getpid();
+    let mut inner = process.inner_exclusive_access();
+    let vpn = va.floor();
+
+    let mut pages = FILE_BACKED_PAGES.exclusive_access();
+    let backing = match pages.get_mut(&(pid, vpn)) {
+        Some(backing) if !backing.faulted => backing,
+        _ => return false,
+    };
+
+    inner.memory_set.page_table_mut().map(
+        vpn,
+        backing.ppn,
+        PTEFlags::from(backing.permission) | PTEFlags::V,
+    );
+    backing.file.read_at(backing.offset, backing.ppn.get_bytes_array());
+    backing.faulted = true;
+    true
+}
+
+/// `sys_munmap(start, len)`.
+///
+/// Only supports unmapping exactly the range a single prior `mmap`/
+/// `mmap_file` call created: we track one `MapArea` per call, and a
+/// genuine partial unmap would need to split that `MapArea` in two, which
+/// this doesn't implement.
+pub fn munmap(start: usize, len: usize) -> isize {
+    let Some((_, _, start_vpn, end_vpn)) = page_range(start, len) else {
+        return -1;
+    };
+
+    let process = current_process();
+    let pid = process.getpid();
+    let mut inner = process.inner_exclusive_access();
+
+    let area_end_vpn = match inner
+        .memory_set
+        .areas
+        .iter()
+        .find(|area| area.vpn_range.get_start() == start_vpn)
+        .map(|area| area.vpn_range.get_end())
+    {
+        Some(end) => end,
+        None => return -1,
+    };
+    if area_end_vpn != end_vpn {
+        return -1;
+    }
+
+    let mut pages = FILE_BACKED_PAGES.exclusive_access();
+    let mut vpn = start_vpn;
+    while vpn < end_vpn {
+        if let Some(backing) = pages.remove(&(pid, vpn)) {
+            // Flush writes back to the file for pages that were actually
+            // faulted in (nothing to flush otherwise) and writably shared.
+            if backing.faulted && backing.writable_shared {
+                backing
+                    .file
+                    .write_at(backing.offset, backing.ppn.get_bytes_array());
+            }
+        }
+        vpn = VirtPageNum::from(usize::from(vpn) + 1);
+    }
+    drop(pages);
+
+    inner.memory_set.remove_area_with_start_vpn(start_vpn);
+    0
+}
@@ -0,0 +1,87 @@
+//! Futex (fast userspace mutex) support
+//!
+//! `Mutex`/`Semaphore` always trap into the kernel, even on the
+//! uncontended path. A futex lets userspace do the uncontended
+//! compare-and-swap itself and only ask the kernel to block (or wake)
+//! a waiter when there is actual contention.
+
+use super::UPSafeCell;
+use crate::mm::{translated_ref, PageTable, VirtAddr};
+use crate::task::{
+    block_current_and_run_next, current_task, current_user_token, wakeup_task, TaskControlBlock,
+};
+use alloc::collections::{BTreeMap, VecDeque};
+use alloc::sync::Arc;
+use lazy_static::lazy_static;
+
+/// wait until the word at `uaddr` no longer equals `val`
+pub const FUTEX_WAIT: u32 = 0;
+/// wake up to `val` tasks waiting on `uaddr`
+pub const FUTEX_WAKE: u32 = 1;
+
+/// userspace did not observe the value it expected; retry the fast path
+pub const EAGAIN: isize = -11;
+/// unsupported futex operation
+pub const EINVAL: isize = -22;
+
+lazy_static! {
+    /// Futex wait queues, keyed by the *physical* address backing the futex word.
+    ///
+    /// Keying on the physical frame rather than the virtual address is the
+    /// critical invariant: two processes that `mmap` the same page and race
+    /// on a futex inside it must land in the same queue even though the
+    /// word sits at different virtual addresses in each address space.
+    static ref FUTEX_QUEUES: UPSafeCell<BTreeMap<usize, VecDeque<Arc<TaskControlBlock>>>> =
+        unsafe { UPSafeCell::new(BTreeMap::new()) };
+}
+
+/// Translate a user virtual address to the physical address that backs it.
+fn futex_key(uaddr: usize) -> usize {
+    let token = current_user_token();
+    let va = VirtAddr::from(uaddr);
+    let page_table = PageTable::from_token(token);
+    let ppn = page_table.translate(va.floor()).unwrap().ppn();
+    (usize::from(ppn) << 12) | va.page_offset()
+}
+
+/// `FUTEX_WAIT(uaddr, expected)`: block if `*uaddr == expected`, else `EAGAIN`
+pub fn futex_wait(uaddr: usize, expected: u32) -> isize {
+    let key = futex_key(uaddr);
+    // The value check and the enqueue must happen as one atomic step under
+    // the same `FUTEX_QUEUES` critical section: if we checked the value
+    // first and only then took the lock, a `futex_wake` landing in between
+    // would find nobody queued yet and the wakeup would be lost forever.
+    let mut queues = FUTEX_QUEUES.exclusive_access();
+    let actual = *translated_ref(current_user_token(), uaddr as *const u32);
+    if actual != expected {
+        return EAGAIN;
+    }
+    queues
+        .entry(key)
+        .or_insert_with(VecDeque::new)
+        .push_back(current_task().unwrap());
+    drop(queues);
+    block_current_and_run_next();
+    0
+}
+
+/// `FUTEX_WAKE(uaddr, n)`: wake up to `n` tasks waiting on `uaddr`
+pub fn futex_wake(uaddr: usize, n: usize) -> isize {
+    let key = futex_key(uaddr);
+    let mut queues = FUTEX_QUEUES.exclusive_access();
+    let mut woken = 0;
+    if let Some(queue) = queues.get_mut(&key) {
+        while woken < n {
+            if let Some(task) = queue.pop_front() {
+                wakeup_task(task);
+                woken += 1;
+            } else {
+                break;
+            }
+        }
+        if queue.is_empty() {
+            queues.remove(&key);
+        }
+    }
+    woken as isize
+}
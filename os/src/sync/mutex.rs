@@ -61,6 +61,9 @@ pub struct MutexBlockingInner {
     locked: bool,
     rid: usize,
     wait_queue: VecDeque<Arc<TaskControlBlock>>,
+    /// the task currently holding the lock, so a waiter blocking in `lock()`
+    /// has someone to donate its priority to
+    holder: Option<Arc<TaskControlBlock>>,
 }
 
 impl MutexBlocking {
@@ -74,6 +77,7 @@ impl MutexBlocking {
                     locked: false,
                     rid,
                     wait_queue: VecDeque::new(),
+                    holder: None,
                 })
             },
         }
@@ -90,33 +94,54 @@ impl Mutex for MutexBlocking {
         let tid = current_task().unwrap().inner_exclusive_access().res.as_ref().unwrap().tid;
         // need 的第一维在创建线程的时候已经扩充好了
         let rid = mutex_inner.rid;
-        // 修改 Need 数组
-        process_inner.ba_need[tid].as_mut().unwrap()[rid] += 1;
+        if process_inner.deadlock_detect_enabled {
+            // 修改 Need 数组
+            process_inner.ba_need[tid].as_mut().unwrap()[rid] += 1;
 
-        if !process_inner.deadlock_check() {
-            warn!("[sys_mutex_lock] deadlock detected.");
-            return false;
+            if !process_inner.deadlock_check() {
+                warn!("[sys_mutex_lock] deadlock detected.");
+                return false;
+            }
         }
         drop(process_inner);
         drop(process);
-        
+
         if mutex_inner.locked {
-            mutex_inner.wait_queue.push_back(current_task().unwrap());
+            let me = current_task().unwrap();
+            // Donate our priority to the holder so a low-priority holder
+            // can't keep a higher-priority waiter (us) blocked indefinitely
+            // while some unrelated medium-priority task runs instead
+            // (priority inversion). This only walks one hop: if the holder
+            // is itself blocked waiting on another lock, there is no
+            // TaskControlBlock -> blocking-lock pointer to walk further, so
+            // a transitive donation through a chain of locks isn't done.
+            if let Some(holder) = mutex_inner.holder.as_ref() {
+                let my_priority = me.inner_exclusive_access().priority;
+                holder.inner_exclusive_access().donate_priority(my_priority);
+            }
+            mutex_inner.wait_queue.push_back(me);
             drop(mutex_inner);
             block_current_and_run_next();
         } else {
-            // 拿到的时候锁就是空的, 遂减少 available
-            *current_process().inner_exclusive_access().ba_available[rid].as_mut().unwrap() -= 1;
+            if current_process().inner_exclusive_access().deadlock_detect_enabled {
+                // 拿到的时候锁就是空的, 遂减少 available
+                *current_process().inner_exclusive_access().ba_available[rid]
+                    .as_mut()
+                    .unwrap() -= 1;
+            }
             mutex_inner.locked = true;
+            mutex_inner.holder = Some(current_task().unwrap());
         }
         // 从别的地方调度回来了或者直接通过了 if 到了这里, 说明锁现在在自己手上
         // 修改 Allocation 数组, 恢复对 Need 的修改
         let process = current_process();
         let mut process_inner = process.inner_exclusive_access();
-        process_inner.ba_need[tid].as_mut().unwrap()[rid] -= 1;
+        if process_inner.deadlock_detect_enabled {
+            process_inner.ba_need[tid].as_mut().unwrap()[rid] -= 1;
 
-        process_inner.ba_allocation[tid].as_mut().unwrap()[rid] += 1;
-        assert!(process_inner.ba_allocation[tid].as_mut().unwrap()[rid] == 1); // 对互斥锁, allo 增加后应该是 1
+            process_inner.ba_allocation[tid].as_mut().unwrap()[rid] += 1;
+            assert!(process_inner.ba_allocation[tid].as_mut().unwrap()[rid] == 1); // 对互斥锁, allo 增加后应该是 1
+        }
 
         true
     }
@@ -131,14 +156,40 @@ impl Mutex for MutexBlocking {
         let mut process_inner = process.inner_exclusive_access();
         let tid = current_task().unwrap().inner_exclusive_access().res.as_ref().unwrap().tid;
         let rid = mutex_inner.rid;
-        process_inner.ba_allocation[tid].as_mut().unwrap()[rid] -= 1;
-        assert!(process_inner.ba_allocation[tid].as_mut().unwrap()[rid] == 0); // 对互斥锁, allo 恢复后应该是 0
+        if process_inner.deadlock_detect_enabled {
+            process_inner.ba_allocation[tid].as_mut().unwrap()[rid] -= 1;
+            assert!(process_inner.ba_allocation[tid].as_mut().unwrap()[rid] == 0); // 对互斥锁, allo 恢复后应该是 0
+        }
+
+        // We're giving up the lock: undo whatever priority was donated to
+        // us while we held it.
+        current_task().unwrap().inner_exclusive_access().restore_priority();
+
         if let Some(waking_task) = mutex_inner.wait_queue.pop_front() {
+            // Re-donate the remaining wait queue's max priority to the new
+            // holder. Without this, a waiter left behind after the first
+            // handoff (donated only to the *previous* holder) would end up
+            // blocked on the new holder with no donation in effect -- the
+            // exact inversion this mechanism exists to prevent.
+            if let Some(max_priority) = mutex_inner
+                .wait_queue
+                .iter()
+                .map(|t| t.inner_exclusive_access().priority)
+                .max()
+            {
+                waking_task
+                    .inner_exclusive_access()
+                    .donate_priority(max_priority);
+            }
+            mutex_inner.holder = Some(Arc::clone(&waking_task));
             wakeup_task(waking_task);
         } else {
-            // 没人要锁, 增加 Available
             mutex_inner.locked = false;
-            *process_inner.ba_available[rid].as_mut().unwrap() += 1;
+            mutex_inner.holder = None;
+            if process_inner.deadlock_detect_enabled {
+                // 没人要锁, 增加 Available
+                *process_inner.ba_available[rid].as_mut().unwrap() += 1;
+            }
         }
     }
 }
@@ -51,7 +51,9 @@ impl Semaphore {
             .unwrap()
             .tid;
         let rid = inner.rid;
-        process_inner.ba_allocation[tid].as_mut().unwrap()[rid] -= 1;
+        if process_inner.deadlock_detect_enabled {
+            process_inner.ba_allocation[tid].as_mut().unwrap()[rid] -= 1;
+        }
 
         inner.count += 1;
         debug!(
@@ -65,7 +67,7 @@ impl Semaphore {
             if let Some(task) = inner.wait_queue.pop_front() {
                 wakeup_task(task);
             }
-        } else {
+        } else if process_inner.deadlock_detect_enabled {
             *process_inner.ba_available[rid].as_mut().unwrap() += 1;
         }
     }
@@ -85,15 +87,17 @@ impl Semaphore {
             .tid;
         // need 的第一维在创建线程的时候已经扩充好了
         let rid = inner.rid;
-        // 修改 Need 数组
-        process_inner.ba_need[tid].as_mut().unwrap()[rid] += 1;
+        if process_inner.deadlock_detect_enabled {
+            // 修改 Need 数组
+            process_inner.ba_need[tid].as_mut().unwrap()[rid] += 1;
 
-        if !process_inner.deadlock_check() {
-            warn!(
-                "[sys_semaphore_down] tid: {}, rid: {} - deadlock detected.",
-                tid, rid
-            );
-            return false;
+            if !process_inner.deadlock_check() {
+                warn!(
+                    "[sys_semaphore_down] tid: {}, rid: {} - deadlock detected.",
+                    tid, rid
+                );
+                return false;
+            }
         }
         drop(process_inner);
         drop(process);
@@ -117,11 +121,13 @@ impl Semaphore {
         // 修改 Allocation 数组, 恢复对 Need 的修改
         let process = current_process();
         let mut process_inner = process.inner_exclusive_access();
-        process_inner.ba_need[tid].as_mut().unwrap()[rid] -= 1;
-        process_inner.ba_allocation[tid].as_mut().unwrap()[rid] += 1;
+        if process_inner.deadlock_detect_enabled {
+            process_inner.ba_need[tid].as_mut().unwrap()[rid] -= 1;
+            process_inner.ba_allocation[tid].as_mut().unwrap()[rid] += 1;
 
-        if after_count >= 0 {
-            *process_inner.ba_available[rid].as_mut().unwrap() -= 1;
+            if after_count >= 0 {
+                *process_inner.ba_available[rid].as_mut().unwrap() -= 1;
+            }
         }
 
         true
@@ -0,0 +1,83 @@
+//! Syscall dispatch: maps a syscall number to the function that implements it.
+//!
+//! Numbering follows the Linux riscv64 syscall table already used by this
+//! kernel's user-space test binaries, with kernel-specific extensions
+//! (futex, mmap-by-file, ...) given unused ids past the standard range.
+
+use fs::*;
+use process::*;
+use sync::*;
+
+use crate::fs::Stat;
+use crate::task::SignalAction;
+
+pub mod fs;
+pub mod process;
+pub mod sync;
+
+const SYSCALL_UNLINKAT: usize = 35;
+const SYSCALL_LINKAT: usize = 37;
+const SYSCALL_OPEN: usize = 56;
+const SYSCALL_CLOSE: usize = 57;
+const SYSCALL_READ: usize = 63;
+const SYSCALL_WRITE: usize = 64;
+const SYSCALL_FSTAT: usize = 80;
+const SYSCALL_EXIT: usize = 93;
+const SYSCALL_YIELD: usize = 124;
+const SYSCALL_KILL: usize = 129;
+const SYSCALL_SIGACTION: usize = 134;
+const SYSCALL_SIGPROCMASK: usize = 135;
+const SYSCALL_SIGRETURN: usize = 139;
+const SYSCALL_SET_PRIORITY: usize = 140;
+const SYSCALL_GET_TIME: usize = 169;
+const SYSCALL_GETPID: usize = 172;
+const SYSCALL_MUNMAP: usize = 215;
+const SYSCALL_FORK: usize = 220;
+const SYSCALL_EXEC: usize = 221;
+const SYSCALL_MMAP: usize = 222;
+const SYSCALL_WAITPID: usize = 260;
+const SYSCALL_SPAWN: usize = 400;
+const SYSCALL_TASK_INFO: usize = 410;
+const SYSCALL_FUTEX: usize = 422;
+const SYSCALL_MMAP_FILE: usize = 423;
+const SYSCALL_ENABLE_DEADLOCK_DETECT: usize = 468;
+
+/// Dispatch a trapped syscall to its implementation.
+///
+/// `args` holds the six argument registers (`a0`..`a5`); callees that take
+/// fewer arguments just ignore the trailing slots.
+pub fn syscall(syscall_id: usize, args: [usize; 6]) -> isize {
+    match syscall_id {
+        SYSCALL_UNLINKAT => sys_unlinkat(args[0] as *const u8),
+        SYSCALL_LINKAT => sys_linkat(args[0] as *const u8, args[1] as *const u8),
+        SYSCALL_OPEN => sys_open(args[0] as *const u8, args[1] as u32),
+        SYSCALL_CLOSE => sys_close(args[0]),
+        SYSCALL_READ => sys_read(args[0], args[1] as *const u8, args[2]),
+        SYSCALL_WRITE => sys_write(args[0], args[1] as *const u8, args[2]),
+        SYSCALL_FSTAT => sys_fstat(args[0], args[1] as *mut Stat),
+        SYSCALL_EXIT => sys_exit(args[0] as i32),
+        SYSCALL_YIELD => sys_yield(),
+        SYSCALL_KILL => sys_kill(args[0], args[1] as u32),
+        SYSCALL_SIGACTION => sys_sigaction(
+            args[0] as i32,
+            args[1] as *const SignalAction,
+            args[2] as *mut SignalAction,
+        ),
+        SYSCALL_SIGPROCMASK => sys_sigprocmask(args[0] as u32),
+        SYSCALL_SIGRETURN => sys_sigreturn(),
+        SYSCALL_SET_PRIORITY => sys_set_priority(args[0] as isize),
+        SYSCALL_GET_TIME => sys_get_time(args[0] as *mut TimeVal, args[1]),
+        SYSCALL_GETPID => sys_getpid(),
+        SYSCALL_MUNMAP => sys_munmap(args[0], args[1]),
+        SYSCALL_FORK => sys_fork(),
+        SYSCALL_EXEC => sys_exec(args[0] as *const u8, args[1] as *const usize),
+        SYSCALL_MMAP => sys_mmap(args[0], args[1], args[2]),
+        SYSCALL_WAITPID => sys_waitpid(args[0] as isize, args[1] as *mut i32),
+        SYSCALL_SPAWN => sys_spawn(args[0] as *const u8),
+        SYSCALL_TASK_INFO => sys_task_info(args[0] as *mut TaskInfo),
+        SYSCALL_FUTEX => sys_futex(args[0], args[1] as u32, args[2] as u32),
+        SYSCALL_MMAP_FILE => sys_mmap_file(args[0], args[1], args[2], args[3], args[4]),
+        SYSCALL_ENABLE_DEADLOCK_DETECT => sys_enable_deadlock_detect(args[0]),
+        _ => panic!("Unsupported syscall_id: {}", syscall_id),
+    }
+}
@@ -6,7 +6,7 @@ use crate::{
     mm::{translated_ref, translated_refmut, translated_str, translated_byte_buffer},
     task::{
         current_process, current_task, current_user_token, exit_current_and_run_next, pid2process,
-        suspend_current_and_run_next, SignalFlags, TaskStatus,
+        suspend_current_and_run_next, SignalAction, SignalFlags, TaskStatus, MAX_SIG,
     }, timer::get_time_us,
 };
 use alloc::{string::String, sync::Arc, vec::Vec};
@@ -228,25 +228,32 @@ pub fn sys_task_info(_ti: *mut TaskInfo) -> isize {
 }
 
 /// mmap syscall
-///
-/// YOUR JOB: Implement mmap.
-pub fn sys_mmap(_start: usize, _len: usize, _port: usize) -> isize {
+pub fn sys_mmap(start: usize, len: usize, port: usize) -> isize {
     trace!(
-        "kernel:pid[{}] sys_mmap NOT IMPLEMENTED",
+        "kernel:pid[{}] sys_mmap",
         current_task().unwrap().process.upgrade().unwrap().getpid()
     );
-    -1
+    crate::mm::mmap::mmap(start, len, port)
+}
+
+/// mmap-a-file-descriptor syscall: like `sys_mmap`, but the pages are
+/// backed by an open file instead of being anonymous, and populated
+/// lazily on first access.
+pub fn sys_mmap_file(start: usize, len: usize, prot: usize, fd: usize, offset: usize) -> isize {
+    trace!(
+        "kernel:pid[{}] sys_mmap_file",
+        current_task().unwrap().process.upgrade().unwrap().getpid()
+    );
+    crate::mm::mmap::mmap_file(start, len, prot, fd, offset)
 }
 
 /// munmap syscall
-///
-/// YOUR JOB: Implement munmap.
-pub fn sys_munmap(_start: usize, _len: usize) -> isize {
+pub fn sys_munmap(start: usize, len: usize) -> isize {
     trace!(
-        "kernel:pid[{}] sys_munmap NOT IMPLEMENTED",
+        "kernel:pid[{}] sys_munmap",
         current_task().unwrap().process.upgrade().unwrap().getpid()
     );
-    -1
+    crate::mm::mmap::munmap(start, len)
 }
 
 /// change data segment size
@@ -269,13 +276,108 @@ pub fn sys_spawn(_path: *const u8) -> isize {
     -1
 }
 
+/// sigaction syscall
+///
+/// Registers `action` as `signum`'s handler, writing the previous one out
+/// through `old_action` first if it's non-null. `SIGKILL` can't be caught or
+/// blocked on a real POSIX system; this kernel only defines that one signal
+/// among the un-catchable set, so it's the only one rejected here.
+pub fn sys_sigaction(
+    signum: i32,
+    action: *const SignalAction,
+    old_action: *mut SignalAction,
+) -> isize {
+    trace!(
+        "kernel:pid[{}] sys_sigaction",
+        current_task().unwrap().process.upgrade().unwrap().getpid()
+    );
+    if signum < 0 || signum as usize >= MAX_SIG {
+        return -1;
+    }
+    let Some(flag) = SignalFlags::from_bits(1 << signum) else {
+        return -1;
+    };
+    if flag == SignalFlags::SIGKILL || action.is_null() {
+        return -1;
+    }
+    let signum = signum as usize;
+    let token = current_user_token();
+    let task = current_task().unwrap();
+    let mut inner = task.inner_exclusive_access();
+    if !old_action.is_null() {
+        *translated_refmut(token, old_action) = inner.sig_actions[signum];
+    }
+    inner.sig_actions[signum] = *translated_ref(token, action);
+    0
+}
+
+/// sigprocmask syscall: sets the calling task's blocked-signal mask,
+/// returning the previous one.
+pub fn sys_sigprocmask(mask: u32) -> isize {
+    trace!(
+        "kernel:pid[{}] sys_sigprocmask",
+        current_task().unwrap().process.upgrade().unwrap().getpid()
+    );
+    let Some(mask) = SignalFlags::from_bits(mask) else {
+        return -1;
+    };
+    let task = current_task().unwrap();
+    let mut inner = task.inner_exclusive_access();
+    let old_mask = inner.sig_mask;
+    inner.sig_mask = mask;
+    old_mask.bits() as isize
+}
+
+/// sigreturn syscall: restore the `TrapContext` and blocked-signal mask
+/// `handle_pending_signals` backed up before entering the current handler.
+pub fn sys_sigreturn() -> isize {
+    trace!(
+        "kernel:pid[{}] sys_sigreturn",
+        current_task().unwrap().process.upgrade().unwrap().getpid()
+    );
+    let task = current_task().unwrap();
+    let mut inner = task.inner_exclusive_access();
+    inner.handling_sig = None;
+    if let Some(mask) = inner.sig_mask_backup.take() {
+        inner.sig_mask = mask;
+    }
+    if let Some(backup) = inner.trap_ctx_backup.take() {
+        *inner.get_trap_cx() = backup;
+    }
+    // `trap_handler` overwrites `a0` with this call's own return value right
+    // after we return, so hand back whatever the interrupted syscall's
+    // return value was meant to be
+    inner.get_trap_cx().x[10] as isize
+}
+
+/// enable_deadlock_detect syscall: flips this process's banker's-algorithm
+/// deadlock detector on or off. While off, `lock`/`down` skip the
+/// availability-matrix bookkeeping and safety check entirely and always
+/// proceed; while on, they run the existing check and refuse on an unsafe
+/// state.
+pub fn sys_enable_deadlock_detect(enabled: usize) -> isize {
+    trace!(
+        "kernel:pid[{}] sys_enable_deadlock_detect",
+        current_task().unwrap().process.upgrade().unwrap().getpid()
+    );
+    current_process().inner_exclusive_access().deadlock_detect_enabled = enabled != 0;
+    0
+}
+
 /// set priority syscall
 ///
-/// YOUR JOB: Set task priority
-pub fn sys_set_priority(_prio: isize) -> isize {
+/// Sets the calling task's stride-scheduling priority. Valid priorities are
+/// `>= 2`; the floor keeps `BIG_STRIDE / priority` from overflowing a single
+/// step's fairness budget. Returns the new priority, or -1 if `prio` is out
+/// of range.
+pub fn sys_set_priority(prio: isize) -> isize {
     trace!(
-        "kernel:pid[{}] sys_set_priority NOT IMPLEMENTED",
+        "kernel:pid[{}] sys_set_priority",
         current_task().unwrap().process.upgrade().unwrap().getpid()
     );
-    -1
+    if prio < 2 {
+        return -1;
+    }
+    current_task().unwrap().inner_exclusive_access().priority = prio as usize;
+    prio
 }
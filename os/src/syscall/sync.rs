@@ -0,0 +1,17 @@
+//! Futex/fast-synchronization syscalls
+
+use crate::sync::futex::{futex_wait, futex_wake, FUTEX_WAIT, FUTEX_WAKE};
+use crate::task::current_task;
+
+/// futex syscall: `FUTEX_WAIT(uaddr, expected)` / `FUTEX_WAKE(uaddr, n)`
+pub fn sys_futex(uaddr: usize, op: u32, val: u32) -> isize {
+    trace!(
+        "kernel:pid[{}] sys_futex",
+        current_task().unwrap().process.upgrade().unwrap().getpid()
+    );
+    match op {
+        FUTEX_WAIT => futex_wait(uaddr, val),
+        FUTEX_WAKE => futex_wake(uaddr, val as usize),
+        _ => -22,
+    }
+}
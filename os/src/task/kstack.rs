@@ -0,0 +1,47 @@
+//! Per-task kernel stacks
+//!
+//! Each task gets its own kernel stack, mapped into kernel space at a slot
+//! indexed by the task's globally-unique id (its [`PidHandle`], shared out of
+//! the same allocator as process pids) with an unmapped guard page below it
+//! to turn a kernel-stack overflow into a page fault instead of silent
+//! corruption of the neighboring stack.
+
+use crate::config::{KERNEL_STACK_SIZE, PAGE_SIZE, TRAMPOLINE};
+use crate::mm::{MapPermission, VirtAddr, KERNEL_SPACE};
+
+fn kernel_stack_position(kstack_id: usize) -> (usize, usize) {
+    let top = TRAMPOLINE - kstack_id * (KERNEL_STACK_SIZE + PAGE_SIZE);
+    let bottom = top - KERNEL_STACK_SIZE;
+    (bottom, top)
+}
+
+/// A task's kernel stack. Mapped on construction, unmapped on drop.
+pub struct KernelStack {
+    kstack_id: usize,
+}
+
+impl KernelStack {
+    pub fn new(kstack_id: usize) -> Self {
+        let (bottom, top) = kernel_stack_position(kstack_id);
+        KERNEL_SPACE.exclusive_access().insert_framed_area(
+            VirtAddr::from(bottom),
+            VirtAddr::from(top),
+            MapPermission::R | MapPermission::W,
+        );
+        Self { kstack_id }
+    }
+
+    /// The stack pointer a freshly-created task should start executing with.
+    pub fn top(&self) -> usize {
+        kernel_stack_position(self.kstack_id).1
+    }
+}
+
+impl Drop for KernelStack {
+    fn drop(&mut self) {
+        let (bottom, _) = kernel_stack_position(self.kstack_id);
+        KERNEL_SPACE
+            .exclusive_access()
+            .remove_area_with_start_vpn(VirtAddr::from(bottom).into());
+    }
+}
@@ -0,0 +1,66 @@
+//! Global task ready-queue and pid-to-process lookup table
+
+use alloc::collections::BTreeMap;
+use alloc::sync::Arc;
+use lazy_static::lazy_static;
+
+use crate::sync::UPSafeCell;
+
+use super::process::ProcessControlBlock;
+use super::scheduler::{Scheduler, StrideScheduler};
+use super::task::{TaskControlBlock, TaskStatus};
+
+pub struct TaskManager {
+    ready_queue: StrideScheduler,
+}
+
+impl TaskManager {
+    pub fn new() -> Self {
+        Self {
+            ready_queue: StrideScheduler::new(),
+        }
+    }
+
+    pub fn add(&mut self, task: Arc<TaskControlBlock>) {
+        self.ready_queue.insert(task);
+    }
+
+    pub fn fetch(&mut self) -> Option<Arc<TaskControlBlock>> {
+        self.ready_queue.pop()
+    }
+}
+
+lazy_static! {
+    static ref TASK_MANAGER: UPSafeCell<TaskManager> = unsafe { UPSafeCell::new(TaskManager::new()) };
+    static ref PID2PCB: UPSafeCell<BTreeMap<usize, Arc<ProcessControlBlock>>> =
+        unsafe { UPSafeCell::new(BTreeMap::new()) };
+}
+
+pub fn add_task(task: Arc<TaskControlBlock>) {
+    TASK_MANAGER.exclusive_access().add(task);
+}
+
+pub fn fetch_task() -> Option<Arc<TaskControlBlock>> {
+    TASK_MANAGER.exclusive_access().fetch()
+}
+
+/// Put a blocked task back on the ready queue.
+pub fn wakeup_task(task: Arc<TaskControlBlock>) {
+    task.inner_exclusive_access().task_status = TaskStatus::Ready;
+    add_task(task);
+}
+
+pub fn pid2process(pid: usize) -> Option<Arc<ProcessControlBlock>> {
+    PID2PCB.exclusive_access().get(&pid).cloned()
+}
+
+pub fn insert_into_pid2process(pid: usize, process: Arc<ProcessControlBlock>) {
+    PID2PCB.exclusive_access().insert(pid, process);
+}
+
+pub fn remove_from_pid2process(pid: usize) {
+    PID2PCB
+        .exclusive_access()
+        .remove(&pid)
+        .expect("pid not in PID2PCB");
+}
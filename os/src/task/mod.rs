@@ -0,0 +1,41 @@
+//! Task (thread) and process management
+
+mod kstack;
+pub mod manager;
+mod pid;
+pub mod process;
+pub mod processor;
+mod scheduler;
+mod signal;
+mod task;
+
+use bitflags::bitflags;
+
+pub use manager::{add_task, fetch_task, pid2process, wakeup_task};
+pub use pid::{pid_alloc, PidHandle};
+pub use process::{ProcessControlBlock, ProcessControlBlockInner};
+pub use processor::{
+    block_current_and_run_next, current_process, current_task, current_trap_cx,
+    current_user_token, exit_current_and_run_next, run_tasks, schedule,
+    suspend_current_and_run_next,
+};
+pub use signal::{handle_pending_signals, SignalAction, SignalActions, MAX_SIG};
+pub use task::{TaskControlBlock, TaskControlBlockInner, TaskStatus};
+
+bitflags! {
+    /// Signals a process can raise against another (`sys_kill`) and a task
+    /// can register a handler for (`sys_sigaction`).
+    pub struct SignalFlags: u32 {
+        const SIGDEF = 1; // Default signal handling
+        const SIGHUP = 1 << 1;
+        const SIGINT = 1 << 2;
+        const SIGQUIT = 1 << 3;
+        const SIGILL = 1 << 4;
+        const SIGTRAP = 1 << 5;
+        const SIGABRT = 1 << 6;
+        const SIGBUS = 1 << 7;
+        const SIGFPE = 1 << 8;
+        const SIGKILL = 1 << 9;
+        const SIGSEGV = 1 << 10;
+    }
+}
@@ -0,0 +1,263 @@
+//! Process control block
+
+use alloc::string::String;
+use alloc::sync::{Arc, Weak};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cell::RefMut;
+
+use riscv::register::sstatus::{self, SPP};
+
+use crate::config::TRAP_CONTEXT;
+use crate::mm::{MemorySet, VirtAddr};
+use crate::sync::UPSafeCell;
+use crate::trap::TrapContext;
+
+use super::manager::insert_into_pid2process;
+use super::pid::{pid_alloc, PidHandle};
+use super::task::TaskControlBlock;
+use super::{add_task, current_task, SignalFlags};
+
+pub struct ProcessControlBlock {
+    pub pid: PidHandle,
+    inner: UPSafeCell<ProcessControlBlockInner>,
+}
+
+pub struct ProcessControlBlockInner {
+    pub is_zombie: bool,
+    pub memory_set: MemorySet,
+    pub parent: Option<Weak<ProcessControlBlock>>,
+    pub children: Vec<Arc<ProcessControlBlock>>,
+    pub exit_code: i32,
+    /// this process's threads, indexed by the process-local tid used to key
+    /// `ba_allocation`/`ba_need`; a `None` slot is a thread that has exited
+    pub tasks: Vec<Option<Arc<TaskControlBlock>>>,
+    /// signals raised against this process (by `sys_kill`) that haven't yet
+    /// been delivered to a task
+    pub signals: SignalFlags,
+
+    // --- banker's-algorithm bookkeeping for deadlock detection ---
+    /// whether `lock()`/`down()` run the availability-matrix bookkeeping and
+    /// safety check below at all; off by default in upstream rCore, on by
+    /// default here per this lab's spec
+    pub deadlock_detect_enabled: bool,
+    /// `Available[rid]`
+    pub ba_available: Vec<Option<isize>>,
+    /// `Allocation[tid][rid]`
+    pub ba_allocation: Vec<Option<Vec<isize>>>,
+    /// `Need[tid][rid]`
+    pub ba_need: Vec<Option<Vec<isize>>>,
+}
+
+impl ProcessControlBlockInner {
+    pub fn get_user_token(&self) -> usize {
+        self.memory_set.token()
+    }
+
+    fn res_count(&self) -> usize {
+        self.ba_available.len()
+    }
+
+    /// Register a new mutex/semaphore backed by `count` units of a fresh
+    /// resource id, extending every existing thread's `Allocation`/`Need`
+    /// rows with a zero entry for it. Returns the new resource id.
+    pub fn alloc_rid(&mut self, count: usize) -> usize {
+        let rid = self.ba_available.len();
+        self.ba_available.push(Some(count as isize));
+        for slot in self.ba_allocation.iter_mut() {
+            if let Some(row) = slot {
+                row.push(0);
+            }
+        }
+        for slot in self.ba_need.iter_mut() {
+            if let Some(row) = slot {
+                row.push(0);
+            }
+        }
+        rid
+    }
+
+    /// Give thread `tid` an `Allocation`/`Need` row sized to the resources
+    /// registered so far.
+    fn alloc_thread_row(&mut self, tid: usize) {
+        let row = vec![0; self.res_count()];
+        while self.ba_allocation.len() <= tid {
+            self.ba_allocation.push(None);
+            self.ba_need.push(None);
+        }
+        self.ba_allocation[tid] = Some(row.clone());
+        self.ba_need[tid] = Some(row);
+    }
+
+    /// Run the banker's safety algorithm over the current
+    /// `Available`/`Allocation`/`Need` matrices. Returns `false` (a would-be
+    /// deadlock) only when detection is enabled and some thread's `Need` can
+    /// never be satisfied by any ordering of releases.
+    pub fn deadlock_check(&self) -> bool {
+        if !self.deadlock_detect_enabled {
+            return true;
+        }
+        let m = self.res_count();
+        let mut work: Vec<isize> = self.ba_available.iter().map(|a| a.unwrap_or(0)).collect();
+        let mut finish = vec![false; self.tasks.len()];
+        for (tid, slot) in self.tasks.iter().enumerate() {
+            if slot.is_none() {
+                finish[tid] = true;
+            }
+        }
+        loop {
+            let mut progressed = false;
+            for tid in 0..finish.len() {
+                if finish[tid] {
+                    continue;
+                }
+                let need = match self.ba_need.get(tid).and_then(|n| n.as_ref()) {
+                    Some(need) => need,
+                    None => continue,
+                };
+                if (0..m).all(|rid| need[rid] <= work[rid]) {
+                    if let Some(alloc) = self.ba_allocation[tid].as_ref() {
+                        for rid in 0..m {
+                            work[rid] += alloc[rid];
+                        }
+                    }
+                    finish[tid] = true;
+                    progressed = true;
+                }
+            }
+            if !progressed {
+                break;
+            }
+        }
+        finish.iter().all(|f| *f)
+    }
+}
+
+impl ProcessControlBlock {
+    pub fn getpid(&self) -> usize {
+        self.pid.0
+    }
+
+    pub fn inner_exclusive_access(&self) -> RefMut<'_, ProcessControlBlockInner> {
+        self.inner.exclusive_access()
+    }
+
+    /// `fork()`: duplicate the address space and the calling thread, as a
+    /// new single-threaded process. Only the calling thread is cloned --
+    /// sibling threads of a multi-threaded parent are not, matching the
+    /// usual POSIX `fork()` semantics this kernel otherwise follows.
+    pub fn fork(self: &Arc<Self>) -> Arc<Self> {
+        let mut parent_inner = self.inner_exclusive_access();
+        let memory_set = MemorySet::from_existing_user(&parent_inner.memory_set);
+        let parent_task = current_task().unwrap();
+        let parent_task_inner = parent_task.inner_exclusive_access();
+
+        let child = Arc::new(Self {
+            pid: pid_alloc(),
+            inner: unsafe {
+                UPSafeCell::new(ProcessControlBlockInner {
+                    is_zombie: false,
+                    memory_set,
+                    parent: Some(Arc::downgrade(self)),
+                    children: Vec::new(),
+                    exit_code: 0,
+                    tasks: Vec::new(),
+                    signals: SignalFlags::empty(),
+                    deadlock_detect_enabled: parent_inner.deadlock_detect_enabled,
+                    ba_available: Vec::new(),
+                    ba_allocation: Vec::new(),
+                    ba_need: Vec::new(),
+                })
+            },
+        });
+
+        let trap_cx_ppn = child
+            .inner_exclusive_access()
+            .memory_set
+            .translate(VirtAddr::from(TRAP_CONTEXT).into())
+            .unwrap()
+            .ppn();
+        let child_task = Arc::new(TaskControlBlock::new(
+            Arc::downgrade(&child),
+            pid_alloc(),
+            0,
+            trap_cx_ppn,
+        ));
+        {
+            let mut child_task_inner = child_task.inner_exclusive_access();
+            child_task_inner.fd_table = parent_task_inner.fd_table.clone();
+            child_task_inner.priority = parent_task_inner.priority;
+            child_task_inner.stride = parent_task_inner.stride;
+            child_task_inner.pass = parent_task_inner.pass;
+            child_task_inner.sig_mask = parent_task_inner.sig_mask;
+            child_task_inner.sig_actions = parent_task_inner.sig_actions;
+            // the trap context is copied wholesale; the caller (`sys_fork`)
+            // overwrites the child's `a0` with 0 afterwards
+            *child_task_inner.get_trap_cx() = *parent_task_inner.get_trap_cx();
+            child_task_inner.get_trap_cx().kernel_sp = child_task.kstack.top();
+        }
+        drop(parent_task_inner);
+
+        let mut child_inner = child.inner_exclusive_access();
+        child_inner.alloc_thread_row(0);
+        child_inner.tasks.push(Some(child_task.clone()));
+        drop(child_inner);
+
+        parent_inner.children.push(Arc::clone(&child));
+        drop(parent_inner);
+
+        insert_into_pid2process(child.getpid(), Arc::clone(&child));
+        add_task(child_task);
+        child
+    }
+
+    /// `exec()`: replace this process's address space with a fresh one
+    /// loaded from `elf_data`, and resume the calling task at its entry
+    /// point. The process is assumed to be single-threaded: other threads
+    /// are not torn down here or by `sys_exec`.
+    ///
+    /// The new `TrapContext` keeps the *old* one's kernel-side fields
+    /// (`kernel_satp`/`kernel_sp`/`trap_handler`): those describe how to get
+    /// back into the kernel on the next trap and have nothing to do with the
+    /// user program being replaced, so they must be read out before
+    /// `memory_set` is overwritten (which drops the old address space,
+    /// including the page backing the old trap context).
+    ///
+    /// `args` is accepted to match the calling convention but argv is not
+    /// yet copied onto the new user stack -- YOUR JOB if a user program
+    /// needs `argc`/`argv`.
+    pub fn exec(self: &Arc<Self>, elf_data: &[u8], _args: Vec<String>) {
+        let (memory_set, user_sp, entry_point) = MemorySet::from_elf(elf_data);
+        let task = current_task().unwrap();
+        let mut task_inner = task.inner_exclusive_access();
+        let old_trap_cx = task_inner.get_trap_cx();
+        let (kernel_satp, kernel_sp, trap_handler) = (
+            old_trap_cx.kernel_satp,
+            old_trap_cx.kernel_sp,
+            old_trap_cx.trap_handler,
+        );
+
+        let mut process_inner = self.inner_exclusive_access();
+        process_inner.memory_set = memory_set;
+        let trap_cx_ppn = process_inner
+            .memory_set
+            .translate(VirtAddr::from(TRAP_CONTEXT).into())
+            .unwrap()
+            .ppn();
+        drop(process_inner);
+
+        task_inner.trap_cx_ppn = trap_cx_ppn;
+        let mut sstatus = sstatus::read();
+        sstatus.set_spp(SPP::User);
+        let mut x = [0usize; 32];
+        x[2] = user_sp;
+        *task_inner.get_trap_cx() = TrapContext {
+            x,
+            sstatus: sstatus.bits(),
+            sepc: entry_point,
+            kernel_satp,
+            kernel_sp,
+            trap_handler,
+        };
+    }
+}
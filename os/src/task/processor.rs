@@ -0,0 +1,192 @@
+//! The running task/process on this core, and the switch in and out of it
+
+use alloc::sync::Arc;
+use lazy_static::lazy_static;
+
+use crate::sync::UPSafeCell;
+use crate::trap::TrapContext;
+
+use super::manager::{fetch_task, remove_from_pid2process};
+use super::process::ProcessControlBlock;
+use super::task::{TaskControlBlock, TaskStatus};
+
+extern "C" {
+    /// Save the registers listed in `TaskContext` for the outgoing task and
+    /// restore them for the incoming one. Defined in `switch.S`.
+    fn __switch(current_task_cx_ptr: *mut TaskContext, next_task_cx_ptr: *const TaskContext);
+}
+
+/// Callee-saved kernel-side registers switched across a `__switch` call.
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct TaskContext {
+    ra: usize,
+    sp: usize,
+    s: [usize; 12],
+}
+
+impl TaskContext {
+    pub fn zero_init() -> Self {
+        Self {
+            ra: 0,
+            sp: 0,
+            s: [0; 12],
+        }
+    }
+
+    pub fn goto_restore(kstack_ptr: usize) -> Self {
+        extern "C" {
+            fn __restore();
+        }
+        Self {
+            ra: __restore as usize,
+            sp: kstack_ptr,
+            s: [0; 12],
+        }
+    }
+}
+
+pub struct Processor {
+    current: Option<Arc<TaskControlBlock>>,
+    idle_task_cx: TaskContext,
+}
+
+impl Processor {
+    pub fn new() -> Self {
+        Self {
+            current: None,
+            idle_task_cx: TaskContext::zero_init(),
+        }
+    }
+
+    fn idle_task_cx_ptr(&mut self) -> *mut TaskContext {
+        &mut self.idle_task_cx as *mut _
+    }
+
+    pub fn take_current(&mut self) -> Option<Arc<TaskControlBlock>> {
+        self.current.take()
+    }
+
+    pub fn current(&self) -> Option<Arc<TaskControlBlock>> {
+        self.current.as_ref().map(Arc::clone)
+    }
+}
+
+lazy_static! {
+    static ref PROCESSOR: UPSafeCell<Processor> = unsafe { UPSafeCell::new(Processor::new()) };
+}
+
+/// Run the scheduling loop: repeatedly pop a ready task and switch into it,
+/// returning to this loop whenever the running task yields control back
+/// (via `schedule`).
+pub fn run_tasks() {
+    loop {
+        let mut processor = PROCESSOR.exclusive_access();
+        if let Some(task) = fetch_task() {
+            let idle_task_cx_ptr = processor.idle_task_cx_ptr();
+            let next_task_cx_ptr = {
+                let mut task_inner = task.inner_exclusive_access();
+                task_inner.task_status = TaskStatus::Running;
+                &task_inner.task_cx as *const TaskContext
+            };
+            processor.current = Some(task);
+            drop(processor);
+            unsafe {
+                __switch(idle_task_cx_ptr, next_task_cx_ptr);
+            }
+        } else {
+            drop(processor);
+        }
+    }
+}
+
+/// Switch out of the calling task and back to the scheduling loop, recording
+/// where to resume it in `switched_task_cx_ptr`.
+pub fn schedule(switched_task_cx_ptr: *mut TaskContext) {
+    let mut processor = PROCESSOR.exclusive_access();
+    let idle_task_cx_ptr = processor.idle_task_cx_ptr();
+    drop(processor);
+    unsafe {
+        __switch(switched_task_cx_ptr, idle_task_cx_ptr);
+    }
+}
+
+pub fn current_task() -> Option<Arc<TaskControlBlock>> {
+    PROCESSOR.exclusive_access().current()
+}
+
+pub fn current_process() -> Arc<ProcessControlBlock> {
+    current_task().unwrap().process.upgrade().unwrap()
+}
+
+pub fn current_user_token() -> usize {
+    current_process().inner_exclusive_access().get_user_token()
+}
+
+pub fn current_trap_cx() -> &'static mut TrapContext {
+    current_task().unwrap().inner_exclusive_access().get_trap_cx()
+}
+
+fn take_current_task() -> Option<Arc<TaskControlBlock>> {
+    PROCESSOR.exclusive_access().take_current()
+}
+
+/// Put the calling task back on the ready queue and switch to another one.
+pub fn suspend_current_and_run_next() {
+    let task = take_current_task().unwrap();
+    let task_cx_ptr = {
+        let mut task_inner = task.inner_exclusive_access();
+        task_inner.task_status = TaskStatus::Ready;
+        &mut task_inner.task_cx as *mut TaskContext
+    };
+    super::manager::add_task(task);
+    schedule(task_cx_ptr);
+}
+
+/// Mark the calling task blocked (not re-queued) and switch to another one.
+/// The caller is responsible for arranging a later `wakeup_task`.
+pub fn block_current_and_run_next() {
+    let task = take_current_task().unwrap();
+    let task_cx_ptr = {
+        let mut task_inner = task.inner_exclusive_access();
+        task_inner.task_status = TaskStatus::Blocked;
+        &mut task_inner.task_cx as *mut TaskContext
+    };
+    schedule(task_cx_ptr);
+}
+
+/// Tear down the calling task (and, if it was the last thread, its process)
+/// and switch to another one. Never returns.
+pub fn exit_current_and_run_next(exit_code: i32) {
+    let task = take_current_task().unwrap();
+    let process = task.process.upgrade().unwrap();
+    let tid = task.inner_exclusive_access().res.as_ref().unwrap().tid;
+
+    {
+        let mut task_inner = task.inner_exclusive_access();
+        task_inner.task_status = TaskStatus::Blocked;
+        task_inner.exit_code = Some(exit_code);
+        task_inner.res = None;
+    }
+    drop(task);
+
+    let mut process_inner = process.inner_exclusive_access();
+    process_inner.tasks[tid] = None;
+    process_inner.ba_allocation[tid] = None;
+    process_inner.ba_need[tid] = None;
+
+    if process_inner.tasks.iter().all(|t| t.is_none()) {
+        process_inner.is_zombie = true;
+        process_inner.exit_code = exit_code;
+        // Orphaned children are simply left parentless: this trimmed tree
+        // has no initproc to adopt them, unlike the full tutorial kernel.
+        process_inner.children.clear();
+        process_inner.memory_set.recycle_data_pages();
+        remove_from_pid2process(process.getpid());
+    }
+    drop(process_inner);
+    drop(process);
+
+    let mut unused = TaskContext::zero_init();
+    schedule(&mut unused as *mut TaskContext);
+}
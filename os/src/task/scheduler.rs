@@ -0,0 +1,143 @@
+//! Pluggable ready-queue scheduling policies
+//!
+//! The processor used to keep a bare `VecDeque<Arc<TaskControlBlock>>` and
+//! schedule round-robin. That hardcodes the policy into the processor loop,
+//! so `sys_set_priority` had nothing to act on. Putting the ready queue
+//! behind a `Scheduler` trait lets a policy (FIFO or stride, both below) be
+//! swapped without touching `run_tasks`; the task manager uses stride.
+
+use alloc::collections::VecDeque;
+use alloc::sync::Arc;
+
+use super::TaskControlBlock;
+
+/// The `BIG_STRIDE` each task's pass is derived from; large enough
+/// that `BIG_STRIDE / priority` stays comfortably non-zero down to the
+/// minimum allowed priority.
+pub const BIG_STRIDE: u64 = 1_000_000;
+
+/// A ready-queue scheduling policy.
+///
+/// Implementors own the queue storage; the processor never reaches into it
+/// directly, so a new policy is a new `impl Scheduler<T>` and nothing else.
+pub trait Scheduler<T> {
+    /// Add a newly-ready task to the queue.
+    fn insert(&mut self, task: T);
+    /// Look at the task that would be picked next, without removing it.
+    fn peek(&self) -> Option<&T>;
+    /// Remove and return the task that should run next.
+    fn pop(&mut self) -> Option<T>;
+    /// Remove a specific task from the queue (e.g. it was killed while ready).
+    fn remove(&mut self, task: &T);
+}
+
+/// The previous behavior: first-in, first-out.
+#[derive(Default)]
+pub struct FifoScheduler {
+    queue: VecDeque<Arc<TaskControlBlock>>,
+}
+
+impl FifoScheduler {
+    /// Create an empty FIFO ready queue.
+    pub fn new() -> Self {
+        Self {
+            queue: VecDeque::new(),
+        }
+    }
+}
+
+impl Scheduler<Arc<TaskControlBlock>> for FifoScheduler {
+    fn insert(&mut self, task: Arc<TaskControlBlock>) {
+        self.queue.push_back(task);
+    }
+
+    fn peek(&self) -> Option<&Arc<TaskControlBlock>> {
+        self.queue.front()
+    }
+
+    fn pop(&mut self) -> Option<Arc<TaskControlBlock>> {
+        self.queue.pop_front()
+    }
+
+    fn remove(&mut self, task: &Arc<TaskControlBlock>) {
+        if let Some(pos) = self
+            .queue
+            .iter()
+            .position(|t| Arc::as_ptr(t) == Arc::as_ptr(task))
+        {
+            self.queue.remove(pos);
+        }
+    }
+}
+
+/// Stride scheduling: always run the ready task with the smallest stride,
+/// then advance that task's stride by `BIG_STRIDE / priority`.
+///
+/// Comparisons use the signed difference between strides (`u64` wrapping
+/// subtraction reinterpreted as `i64`) rather than a plain `<`, so that a
+/// stride wrapping around `u64::MAX` is still ordered correctly. This is
+/// safe as long as no two ready tasks' strides differ by more than
+/// `u64::MAX / 2`, which holds here because `priority >= 2` caps each step
+/// at `BIG_STRIDE / 2`.
+#[derive(Default)]
+pub struct StrideScheduler {
+    queue: VecDeque<Arc<TaskControlBlock>>,
+}
+
+impl StrideScheduler {
+    /// Create an empty stride-scheduled ready queue.
+    pub fn new() -> Self {
+        Self {
+            queue: VecDeque::new(),
+        }
+    }
+}
+
+impl Scheduler<Arc<TaskControlBlock>> for StrideScheduler {
+    fn insert(&mut self, task: Arc<TaskControlBlock>) {
+        self.queue.push_back(task);
+    }
+
+    fn peek(&self) -> Option<&Arc<TaskControlBlock>> {
+        self.min_stride_index().map(|idx| &self.queue[idx])
+    }
+
+    fn pop(&mut self) -> Option<Arc<TaskControlBlock>> {
+        let idx = self.min_stride_index()?;
+        let task = self.queue.remove(idx).unwrap();
+        let mut inner = task.inner_exclusive_access();
+        inner.pass = BIG_STRIDE / inner.priority as u64;
+        inner.stride = inner.stride.wrapping_add(inner.pass);
+        drop(inner);
+        Some(task)
+    }
+
+    fn remove(&mut self, task: &Arc<TaskControlBlock>) {
+        if let Some(pos) = self
+            .queue
+            .iter()
+            .position(|t| Arc::as_ptr(t) == Arc::as_ptr(task))
+        {
+            self.queue.remove(pos);
+        }
+    }
+}
+
+impl StrideScheduler {
+    fn min_stride_index(&self) -> Option<usize> {
+        if self.queue.is_empty() {
+            return None;
+        }
+        let mut min_idx = 0;
+        let mut min_stride = self.queue[0].inner_exclusive_access().stride;
+        for (idx, task) in self.queue.iter().enumerate().skip(1) {
+            let stride = task.inner_exclusive_access().stride;
+            // signed wrap-around comparison: stride < min_stride
+            if (stride.wrapping_sub(min_stride) as i64) < 0 {
+                min_idx = idx;
+                min_stride = stride;
+            }
+        }
+        Some(min_idx)
+    }
+}
@@ -0,0 +1,116 @@
+//! Signal delivery: per-task handler registration and trap-time dispatch
+//!
+//! `sys_kill` (in `syscall/process.rs`) only ever raises bits in the
+//! *process*-level `signals` field -- this kernel doesn't give individual
+//! threads their own pending set. Delivery is per-task, though: each task
+//! has its own blocked mask and handler table, so [`handle_pending_signals`]
+//! is what turns a process-level pending bit into either a handler
+//! invocation or a default action on the calling task. It's called from
+//! `trap_return` on every return to user mode.
+
+use super::{current_process, current_task, exit_current_and_run_next, SignalFlags};
+
+/// number of handler slots: one per bit of [`SignalFlags`]' `u32`
+pub const MAX_SIG: usize = 32;
+
+/// A registered handler for one signal, as set by `sys_sigaction`.
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct SignalAction {
+    /// user-space handler entry point; `0` means "no handler, default action"
+    pub handler: usize,
+    /// additional signals to block for the duration of this handler
+    pub mask: SignalFlags,
+}
+
+impl Default for SignalAction {
+    fn default() -> Self {
+        Self {
+            handler: 0,
+            mask: SignalFlags::empty(),
+        }
+    }
+}
+
+/// Per-task `SignalAction` table, indexed by signal number (e.g. `SIGINT`,
+/// bit `1 << 2`, lives at index `2`).
+#[derive(Copy, Clone)]
+pub struct SignalActions([SignalAction; MAX_SIG]);
+
+impl Default for SignalActions {
+    fn default() -> Self {
+        Self([SignalAction::default(); MAX_SIG])
+    }
+}
+
+impl core::ops::Index<usize> for SignalActions {
+    type Output = SignalAction;
+    fn index(&self, signum: usize) -> &SignalAction {
+        &self.0[signum]
+    }
+}
+
+impl core::ops::IndexMut<usize> for SignalActions {
+    fn index_mut(&mut self, signum: usize) -> &mut SignalAction {
+        &mut self.0[signum]
+    }
+}
+
+/// Signals whose default (no-handler-registered) action is to terminate the
+/// task rather than being silently ignored.
+fn is_default_fatal(signal: SignalFlags) -> bool {
+    signal.intersects(
+        SignalFlags::SIGINT
+            | SignalFlags::SIGILL
+            | SignalFlags::SIGABRT
+            | SignalFlags::SIGBUS
+            | SignalFlags::SIGFPE
+            | SignalFlags::SIGKILL
+            | SignalFlags::SIGSEGV,
+    )
+}
+
+/// Check the calling task's pending-and-unblocked signals (from its
+/// process's `signals` field) and act on at most one of them: deliver it to
+/// a registered handler or apply the default action (terminate, or discard
+/// it if the default is to ignore it). Blocked signals are left pending.
+pub fn handle_pending_signals() {
+    let task = current_task().unwrap();
+    let mut task_inner = task.inner_exclusive_access();
+    if task_inner.handling_sig.is_some() {
+        // already inside a handler: stays pending until it `sigreturn`s
+        return;
+    }
+
+    let process = current_process();
+    let mut process_inner = process.inner_exclusive_access();
+    // bitflags 1.x has no `.iter()`; pull the lowest deliverable bit out by
+    // hand instead
+    let deliverable = (process_inner.signals & !task_inner.sig_mask).bits();
+    if deliverable == 0 {
+        return;
+    }
+    let signum = deliverable.trailing_zeros() as usize;
+    let signal = SignalFlags::from_bits(1 << signum).unwrap();
+    process_inner.signals -= signal;
+    drop(process_inner);
+    drop(process);
+
+    let action = task_inner.sig_actions[signum];
+    if action.handler == 0 {
+        if is_default_fatal(signal) {
+            drop(task_inner);
+            drop(task);
+            exit_current_and_run_next(-(signum as i32));
+        }
+        return;
+    }
+
+    task_inner.handling_sig = Some(signum);
+    task_inner.sig_mask_backup = Some(task_inner.sig_mask);
+    task_inner.sig_mask |= action.mask | signal;
+    let trap_cx = task_inner.get_trap_cx();
+    task_inner.trap_ctx_backup = Some(*trap_cx);
+    trap_cx.sepc = action.handler;
+    trap_cx.x[10] = signum;
+}
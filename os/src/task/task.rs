@@ -0,0 +1,161 @@
+//! Task control block: the kernel-side state for a single schedulable thread
+
+use alloc::sync::{Arc, Weak};
+use alloc::vec::Vec;
+use core::cell::RefMut;
+
+use crate::fs::File;
+use crate::mm::PhysPageNum;
+use crate::sync::UPSafeCell;
+use crate::trap::TrapContext;
+
+use super::kstack::KernelStack;
+use super::pid::PidHandle;
+use super::process::ProcessControlBlock;
+use super::processor::TaskContext;
+use super::scheduler::BIG_STRIDE;
+use super::signal::SignalActions;
+use super::SignalFlags;
+
+/// default priority assigned to a freshly-created task; `sys_set_priority`
+/// can raise or lower it (subject to the `>= 2` floor the scheduler needs)
+const DEFAULT_PRIORITY: usize = 16;
+
+/// Resources owned by a task for as long as it's running: just which of the
+/// owning process's thread slots it occupies, for now.
+pub struct TaskUserRes {
+    pub tid: usize,
+    pub process: Weak<ProcessControlBlock>,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum TaskStatus {
+    Ready,
+    Running,
+    Blocked,
+}
+
+pub struct TaskControlBlock {
+    /// globally-unique task id, allocated out of the same pool as process
+    /// pids; distinct from the owning process's own pid. Also used to place
+    /// this task's kernel stack.
+    pub pid: PidHandle,
+    pub process: Weak<ProcessControlBlock>,
+    pub kstack: KernelStack,
+    inner: UPSafeCell<TaskControlBlockInner>,
+}
+
+pub struct TaskControlBlockInner {
+    pub res: Option<TaskUserRes>,
+    pub trap_cx_ppn: PhysPageNum,
+    pub task_cx: TaskContext,
+    pub task_status: TaskStatus,
+    pub exit_code: Option<i32>,
+    pub fd_table: Vec<Option<Arc<dyn File>>>,
+    /// this task's effective scheduling priority; `>= 2`, defaults to
+    /// [`DEFAULT_PRIORITY`], temporarily raised by mutex priority donation
+    pub priority: usize,
+    /// the priority to restore once a donated priority is released; `None`
+    /// when nothing is currently donated
+    pub original_priority: Option<usize>,
+    pub stride: u64,
+    pub pass: u64,
+
+    // --- signal delivery ---
+    /// signals currently blocked from delivery to this task
+    pub sig_mask: SignalFlags,
+    /// this task's registered `sys_sigaction` handlers, by signal number
+    pub sig_actions: SignalActions,
+    /// signal number of the handler currently running, if any; blocks
+    /// further delivery until `sys_sigreturn`
+    pub handling_sig: Option<usize>,
+    /// `sig_mask` from just before entering the handler named by
+    /// `handling_sig`, restored by `sys_sigreturn`
+    pub sig_mask_backup: Option<SignalFlags>,
+    /// the interrupted `TrapContext`, saved by `handle_pending_signals` and
+    /// restored by `sys_sigreturn`
+    pub trap_ctx_backup: Option<TrapContext>,
+}
+
+impl TaskControlBlockInner {
+    pub fn get_trap_cx(&self) -> &'static mut TrapContext {
+        self.trap_cx_ppn.get_mut()
+    }
+
+    pub fn alloc_fd(&mut self) -> usize {
+        if let Some(fd) = (0..self.fd_table.len()).find(|fd| self.fd_table[*fd].is_none()) {
+            fd
+        } else {
+            self.fd_table.push(None);
+            self.fd_table.len() - 1
+        }
+    }
+
+    /// Temporarily raise this task's priority to at least `priority`,
+    /// remembering the original so it can be undone by [`restore_priority`].
+    /// A second donation while one is already in effect just raises the
+    /// ceiling further; the original priority from before the *first*
+    /// donation is still what gets restored.
+    ///
+    /// [`restore_priority`]: Self::restore_priority
+    pub fn donate_priority(&mut self, priority: usize) {
+        if self.original_priority.is_none() {
+            self.original_priority = Some(self.priority);
+        }
+        if priority > self.priority {
+            self.priority = priority;
+        }
+    }
+
+    /// Undo [`donate_priority`], if a donation is in effect.
+    ///
+    /// [`donate_priority`]: Self::donate_priority
+    pub fn restore_priority(&mut self) {
+        if let Some(original) = self.original_priority.take() {
+            self.priority = original;
+        }
+    }
+}
+
+impl TaskControlBlock {
+    pub fn inner_exclusive_access(&self) -> RefMut<'_, TaskControlBlockInner> {
+        self.inner.exclusive_access()
+    }
+
+    pub fn new(
+        process: Weak<ProcessControlBlock>,
+        pid: PidHandle,
+        tid: usize,
+        trap_cx_ppn: PhysPageNum,
+    ) -> Self {
+        let kstack = KernelStack::new(pid.0);
+        let kstack_top = kstack.top();
+        Self {
+            process: process.clone(),
+            pid,
+            kstack,
+            inner: unsafe {
+                UPSafeCell::new(TaskControlBlockInner {
+                    res: Some(TaskUserRes { tid, process }),
+                    trap_cx_ppn,
+                    // resumes by restoring the trap context set up right
+                    // after construction (by `ProcessControlBlock::fork`/
+                    // `exec`), jumping straight back to user mode
+                    task_cx: TaskContext::goto_restore(kstack_top),
+                    task_status: TaskStatus::Ready,
+                    exit_code: None,
+                    fd_table: Vec::new(),
+                    priority: DEFAULT_PRIORITY,
+                    original_priority: None,
+                    stride: 0,
+                    pass: BIG_STRIDE / DEFAULT_PRIORITY as u64,
+                    sig_mask: SignalFlags::empty(),
+                    sig_actions: SignalActions::default(),
+                    handling_sig: None,
+                    sig_mask_backup: None,
+                    trap_ctx_backup: None,
+                })
+            },
+        }
+    }
+}
@@ -0,0 +1,83 @@
+//! Trap handling: register/CSR state saved across a trap, and what to do
+//! with each kind of trap once we're back in the kernel
+
+use riscv::register::scause::{self, Exception, Trap};
+use riscv::register::stval;
+
+use crate::mm::mmap::handle_mmap_page_fault;
+use crate::mm::VirtAddr;
+use crate::syscall::syscall;
+use crate::task::{
+    current_trap_cx, current_user_token, exit_current_and_run_next, handle_pending_signals,
+};
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct TrapContext {
+    pub x: [usize; 32],
+    pub sstatus: usize,
+    pub sepc: usize,
+    pub kernel_satp: usize,
+    pub kernel_sp: usize,
+    pub trap_handler: usize,
+}
+
+/// Entered from `__alltraps` (assembly, not present in this trimmed tree)
+/// with the kernel stack already holding the trapped task's `TrapContext`.
+/// Dispatches on the trap cause and falls through to `trap_return` to
+/// resume the task (or its replacement, if it was killed).
+#[no_mangle]
+pub fn trap_handler() -> ! {
+    let scause = scause::read();
+    let stval = stval::read();
+    match scause.cause() {
+        Trap::Exception(Exception::UserEnvCall) => {
+            let mut cx = current_trap_cx();
+            cx.sepc += 4;
+            let result = syscall(
+                cx.x[17],
+                [cx.x[10], cx.x[11], cx.x[12], cx.x[13], cx.x[14], cx.x[15]],
+            );
+            // re-fetch: a task-replacing syscall (exec) may have swapped in
+            // a brand new TrapContext out from under the one we started with
+            cx = current_trap_cx();
+            cx.x[10] = result as usize;
+        }
+        Trap::Exception(Exception::StorePageFault)
+        | Trap::Exception(Exception::LoadPageFault)
+        | Trap::Exception(Exception::InstructionPageFault) => {
+            if !handle_mmap_page_fault(VirtAddr::from(stval)) {
+                warn!("[kernel] unhandled page fault at {:#x}, killing task", stval);
+                exit_current_and_run_next(-2);
+            }
+        }
+        _ => {
+            warn!(
+                "[kernel] unsupported trap {:?}, stval = {:#x}, killing task",
+                scause.cause(),
+                stval
+            );
+            exit_current_and_run_next(-2);
+        }
+    }
+    trap_return();
+}
+
+/// Resume user execution. Stock trampoline machinery (`__restore`, jumping
+/// via the fixed `TRAMPOLINE` VA) lives outside this trimmed tree; this is
+/// the real entry point the rest of this series calls into.
+#[no_mangle]
+pub fn trap_return() -> ! {
+    // deliver (at most) one pending-and-unblocked signal before resuming
+    // user code, possibly redirecting it into a registered handler
+    handle_pending_signals();
+
+    extern "C" {
+        fn __restore(trap_cx_ptr: usize, user_satp: usize) -> !;
+    }
+    let trap_cx_ptr = crate::config::TRAP_CONTEXT;
+    let user_satp = current_user_token();
+    unsafe {
+        __restore(trap_cx_ptr, user_satp);
+    }
+}